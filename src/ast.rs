@@ -0,0 +1,52 @@
+use std::fmt;
+
+/// The parsed shape of an expression node. Binary operators are kept as a
+/// `&'static str` (rather than a `char`) so single-char (`+`) and two-char
+/// (`<=`, `!=`) spellings share the same field — see `shuntingyard::OperatorTable::op_str`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AstType {
+    Number(f64),
+    Ident(String),
+    Func(String, Vec<AstNode>),
+    Binary(&'static str, Box<AstNode>, Box<AstNode>),
+    Prefix(char, Box<AstNode>),
+    Postfix(char, Box<AstNode>),
+    Parens(Box<AstNode>),
+    /// `a..b`, kept distinct from `Binary` since a range isn't itself a
+    /// value computed from an operator char.
+    Range(Box<AstNode>, Box<AstNode>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstNode {
+    pub typ: AstType,
+    pub pos: u32,
+}
+
+impl AstNode {
+    pub fn new(typ: AstType, pos: u32) -> AstNode {
+        AstNode { typ: typ, pos: pos }
+    }
+}
+
+impl fmt::Display for AstNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.typ {
+            AstType::Number(v) => write!(f, "{}", v),
+            AstType::Ident(ref name) => write!(f, "{}", name),
+            AstType::Func(ref name, ref args) => {
+                try!(write!(f, "{}(", name));
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 { try!(write!(f, ", ")); }
+                    try!(write!(f, "{}", arg));
+                }
+                write!(f, ")")
+            },
+            AstType::Binary(op, ref lhs, ref rhs) => write!(f, "{} {} {}", lhs, op, rhs),
+            AstType::Prefix(op, ref rhs) => write!(f, "{}{}", op, rhs),
+            AstType::Postfix(op, ref lhs) => write!(f, "{}{}", lhs, op),
+            AstType::Parens(ref inner) => write!(f, "({})", inner),
+            AstType::Range(ref lo, ref hi) => write!(f, "{}..{}", lo, hi),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+
+use lexer::TokenType;
+
+/// An error produced while parsing an expression. Every variant carries the
+/// byte position(s) in the source where the problem was found (see `span`)
+/// instead of forcing callers to string-match a formatted message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError<'a> {
+    UnexpectedToken { found: TokenType<'a>, expected: Option<TokenType<'a>>, pos: u32 },
+    UnexpectedEof { pos: u32 },
+    UnclosedParen { open_pos: u32 },
+    UnknownOperator { op: char, pos: u32 },
+}
+
+impl<'a> ParseError<'a> {
+    /// The `(start, end)` byte range in the source this error covers, so
+    /// editors/tools can underline the offending region.
+    pub fn span(&self) -> (u32, u32) {
+        match *self {
+            ParseError::UnexpectedToken { pos, .. } => (pos, pos),
+            ParseError::UnexpectedEof { pos } => (pos, pos),
+            ParseError::UnclosedParen { open_pos } => (open_pos, open_pos),
+            ParseError::UnknownOperator { pos, .. } => (pos, pos),
+        }
+    }
+}
+
+impl<'a> fmt::Display for ParseError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::UnexpectedToken { found, expected: Some(expected), pos } =>
+                write!(f, "Expected {:?} of expression, but got {:?} at position {:?}", expected, found, pos),
+            ParseError::UnexpectedToken { found, expected: None, pos } =>
+                write!(f, "Unexpected token {:?} at position {:?}", found, pos),
+            ParseError::UnexpectedEof { pos } =>
+                write!(f, "Unexpected end of input at position {:?}", pos),
+            ParseError::UnclosedParen { open_pos } =>
+                write!(f, "Unclosed parenthesis opened at position {:?}", open_pos),
+            ParseError::UnknownOperator { op, pos } =>
+                write!(f, "Unknown operator {:?} at position {:?}", op, pos),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use error::ParseError;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TokenType<'a> {
+    Number(f64),
+    Ident(&'a str),
+    /// A single-char operator or punctuation token (`+`, `(`, `,`, ...).
+    OpSingle(char),
+    /// A two-char operator lexeme recognized as one token — see `OPS_DOUBLE`.
+    OpDouble(char, char),
+    End,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub typ: TokenType<'a>,
+    pub pos: u32,
+}
+
+/// Two-char operator spellings the lexer merges into a single `OpDouble`
+/// token instead of two `OpSingle` ones. A leading char not found here
+/// (e.g. a lone `!` not followed by `=`, or a lone `.` not followed by
+/// another `.`) falls through to `OpSingle` as usual, so e.g. `3!` still
+/// lexes as `Number(3)` `OpSingle('!')` rather than reaching for a second
+/// char that isn't there.
+const OPS_DOUBLE: [(char, char); 4] = [('<', '='), ('>', '='), ('!', '='), ('.', '.')];
+
+pub struct Lexer<'a> {
+    text: &'a str,
+    pos: u32,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(text: &'a str) -> Lexer<'a> {
+        Lexer { text: text, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos as usize..]
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn advance_char(&mut self, ch: char) {
+        self.pos += ch.len_utf8() as u32;
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(ch) = self.peek_char() {
+            if !ch.is_whitespace() { break; }
+            self.advance_char(ch);
+        }
+    }
+
+    fn lex_number(&mut self, start: u32) -> Token<'a> {
+        while let Some(ch) = self.peek_char() {
+            if !ch.is_digit(10) { break; }
+            self.advance_char(ch);
+        }
+        let text = &self.text[start as usize..self.pos as usize];
+        Token { typ: TokenType::Number(text.parse().unwrap_or(0.0)), pos: start }
+    }
+
+    fn lex_ident(&mut self, start: u32) -> Token<'a> {
+        while let Some(ch) = self.peek_char() {
+            if !ch.is_alphanumeric() && ch != '_' { break; }
+            self.advance_char(ch);
+        }
+        Token { typ: TokenType::Ident(&self.text[start as usize..self.pos as usize]), pos: start }
+    }
+
+    pub fn next_token(&mut self) -> Result<Token<'a>, ParseError<'a>> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek_char() {
+            None => Ok(Token { typ: TokenType::End, pos: start }),
+            Some(ch) if ch.is_digit(10) => Ok(self.lex_number(start)),
+            Some(ch) if ch.is_alphabetic() || ch == '_' => Ok(self.lex_ident(start)),
+            Some(ch0) => {
+                self.advance_char(ch0);
+                if let Some(ch1) = self.peek_char() {
+                    if OPS_DOUBLE.contains(&(ch0, ch1)) {
+                        self.advance_char(ch1);
+                        return Ok(Token { typ: TokenType::OpDouble(ch0, ch1), pos: start });
+                    }
+                }
+                Ok(Token { typ: TokenType::OpSingle(ch0), pos: start })
+            },
+        }
+    }
+}
@@ -1,102 +1,219 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
 use ast::AstNode;
-use ast::AstType::{Number, Ident, Func, Binary, Prefix, Postfix, Parens};
+use ast::AstType::{Number, Ident, Func, Binary, Prefix, Postfix, Parens, Range};
 use error::ParseError;
 use lexer::{Lexer, Token, TokenType};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum Op {
     Sentinel(u32),
-    Binary(char, u32),
+    Binary(&'static str, u32),
     Prefix(char, u32),
     Postfix(char, u32),
+    /// `a..b`, the lowest-precedence infix operator; kept separate from
+    /// `Binary` because it builds a dedicated `AstType::Range` node rather
+    /// than a generic binary-operator node.
+    Range(u32),
 }
 
 fn is_sentinel(op: &Option<&Op>) -> bool {
     if let &Some(&Op::Sentinel(_)) = op { true } else { false }
 }
 
-struct ShuntingYard<'a> {
+/// The infix operator `parse_e` sees upcoming, before it's placed on `op_stack`.
+enum InfixOp {
+    Binary(&'static str),
+    Range,
+}
+
+struct ShuntingYard<'a, 'o> {
     lexer: Lexer<'a>,
     next: Token<'a>,
     op_stack: Vec<Op>,
     exp_stack: Vec<AstNode>,
+    ops: &'o OperatorTable,
 }
 
-const OPS_BINARY: [char; 5] = ['+', '-', '*', '/', '^'];
-const OPS_PREFIX: [char; 1] = ['-'];
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Assoc { Left, Right }
+
+/// What role an operator char plays; a char may be registered under more
+/// than one kind (e.g. `-` is both `Prefix` and `Binary`).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum OperatorKind { Binary, Prefix, Postfix }
 
-fn is_binary(op_char: char) -> bool {
-    OPS_BINARY.contains(&op_char)
+#[derive(Copy, Clone, Debug)]
+pub struct OperatorSpec {
+    pub kind: OperatorKind,
+    pub precedence: u32,
+    pub assoc: Assoc,
 }
-fn is_prefix(op_char: char) -> bool {
-    OPS_PREFIX.contains(&op_char)
+
+/// A data-driven table of operator fixities, so callers can configure
+/// `ShuntingYard` with their own operators instead of editing this module.
+/// Double-char operators (`<=`, `>=`, `!=`) aren't representable as a single
+/// `char` key; they're recognized separately in `double_op_prec` below with
+/// fixed, left-associative precedences equal to those the `Default` table
+/// gives their single-char siblings (`<=`/`>=` like `<`/`>`, `!=` like `=`).
+/// A custom table does not change these, and they are accepted whether or
+/// not the table defines `<`, `>` or `=`.
+pub struct OperatorTable {
+    entries: HashMap<(char, OperatorKind), OperatorSpec>,
+    /// Cache of the `&'static str` leaked for each non-built-in binary
+    /// operator char seen so far (see `op_str`), so a custom operator like
+    /// `%` is leaked at most once no matter how many times it occurs in the
+    /// input, rather than once per token.
+    interned: RefCell<HashMap<char, &'static str>>,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum Assoc { Left, Right }
+impl OperatorTable {
+    pub fn new() -> OperatorTable {
+        OperatorTable { entries: HashMap::new(), interned: RefCell::new(HashMap::new()) }
+    }
+
+    pub fn define(&mut self, ch: char, kind: OperatorKind, precedence: u32, assoc: Assoc) -> &mut OperatorTable {
+        self.entries.insert((ch, kind), OperatorSpec { kind: kind, precedence: precedence, assoc: assoc });
+        self
+    }
 
-fn assoc(op: &Op) -> Assoc {
-    if let &Op::Binary(ch, _) = op {
+    fn lookup(&self, ch: char, kind: OperatorKind) -> Option<OperatorSpec> {
+        self.entries.get(&(ch, kind)).map(|spec| *spec)
+    }
+
+    fn is_binary(&self, ch: char) -> bool {
+        self.lookup(ch, OperatorKind::Binary).is_some()
+    }
+    fn is_prefix(&self, ch: char) -> bool {
+        self.lookup(ch, OperatorKind::Prefix).is_some()
+    }
+    fn is_postfix(&self, ch: char) -> bool {
+        self.lookup(ch, OperatorKind::Postfix).is_some()
+    }
+
+    /// Canonical, 'static spelling of a single-char binary operator, used so
+    /// `Op::Binary` and `AstType::Binary` can represent both single- and
+    /// double-char operators (e.g. `+` and `<=`) with the same `&str` field.
+    /// Built-in chars map to static literals; any other char registered in
+    /// this table is leaked into a `&'static str` the first time it's seen
+    /// and cached in `interned`, so repeated occurrences of the same custom
+    /// operator reuse that one allocation instead of leaking a fresh one
+    /// per token.
+    fn op_str(&self, ch: char) -> &'static str {
         match ch {
-            '^' => return Assoc::Right,
+            '+' => "+", '-' => "-", '*' => "*", '/' => "/", '^' => "^",
+            '<' => "<", '>' => ">", '=' => "=", '&' => "&", '|' => "|",
             _ => {
-                assert!(['+', '-', '*', '/'].contains(&ch), "Unknown operator associativity {}", ch);
-                return Assoc::Left
-            }
-        };
+                if let Some(s) = self.interned.borrow().get(&ch) {
+                    return s;
+                }
+                let mut owned = String::new();
+                owned.push(ch);
+                let leaked: &'static str = Box::leak(owned.into_boxed_str());
+                self.interned.borrow_mut().insert(ch, leaked);
+                leaked
+            },
+        }
     }
-    panic!("Operator {:?} does not have associativity", op)
 }
 
-fn prec(op: &Op) -> i32 {
-    match op {
-        &Op::Sentinel(_) => 0,
-        &Op::Binary('+', _) | &Op::Binary('-', _) => 1,
-        &Op::Binary('*', _) | &Op::Binary('/', _) => 2,
-        &Op::Prefix('-', _) => 3,
-        &Op::Binary('^', _) => 4,
-        _ => panic!("Unexpected operator {:?}", op),
+impl Default for OperatorTable {
+    /// The arithmetic, relational, equality and boolean operators this
+    /// module has always understood. Precedence ladder, lowest to highest:
+    ///   range (`..`) < boolean (`&` `|`) < equality (`=` `!=`) < relational
+    ///   (`<` `<=` `>` `>=`) < additive (`+` `-`) < multiplicative (`*` `/`)
+    ///   < unary `-` < `^` < postfix `!`
+    /// so `a < b & c` groups as `(a < b) & c` and `1+1..n*2` groups as
+    /// `(1+1)..(n*2)`. The `..` range operator isn't in this table (see
+    /// `Op::Range`), since it always sits at the bottom of the ladder.
+    fn default() -> OperatorTable {
+        let mut ops = OperatorTable::new();
+        ops.define('&', OperatorKind::Binary, 2, Assoc::Left);
+        ops.define('|', OperatorKind::Binary, 2, Assoc::Left);
+        ops.define('=', OperatorKind::Binary, 3, Assoc::Left);
+        ops.define('<', OperatorKind::Binary, 4, Assoc::Left);
+        ops.define('>', OperatorKind::Binary, 4, Assoc::Left);
+        ops.define('+', OperatorKind::Binary, 5, Assoc::Left);
+        ops.define('-', OperatorKind::Binary, 5, Assoc::Left);
+        ops.define('*', OperatorKind::Binary, 6, Assoc::Left);
+        ops.define('/', OperatorKind::Binary, 6, Assoc::Left);
+        ops.define('-', OperatorKind::Prefix, 7, Assoc::Left);
+        ops.define('^', OperatorKind::Binary, 8, Assoc::Right);
+        ops.define('!', OperatorKind::Postfix, 9, Assoc::Left);
+        ops
     }
 }
 
-#[inline(always)]
-fn has_greater_prec(op1: &Op, op2: &Op) -> bool {
-    let prec1 = prec(&op1);
-    let prec2 = prec(&op2);
-    prec1 > prec2 || (prec1 == prec2 && assoc(op1) == Assoc::Left)
+/// The double-char spelling for a recognized two-char binary operator, if any.
+fn double_op_str(ch0: char, ch1: char) -> Option<&'static str> {
+    match (ch0, ch1) {
+        ('<', '=') => Some("<="),
+        ('>', '=') => Some(">="),
+        ('!', '=') => Some("!="),
+        _ => None,
+    }
 }
 
-impl<'a> ShuntingYard<'a> {
-    fn parse(&mut self) -> Result<AstNode, ParseError> {
+/// The fixed precedence of a double-char operator: that of its single-char
+/// sibling in the `Default` table, whatever table the parser was given.
+fn double_op_prec(op: &'static str) -> Option<i32> {
+    match op {
+        "<=" | ">=" => Some(4),
+        "!=" => Some(3),
+        _ => None,
+    }
+}
+
+impl<'a, 'o> ShuntingYard<'a, 'o> {
+    fn parse(&mut self) -> Result<AstNode, ParseError<'a>> {
         try!(self.parse_e());
         try!(self.expect(TokenType::End));
         assert_eq!(self.exp_stack.len(), 1);
         assert_eq!(self.op_stack.len(), 1);
-        Ok::<AstNode, ParseError>(self.exp_stack.pop().unwrap())
+        Ok::<AstNode, ParseError<'a>>(self.exp_stack.pop().unwrap())
     }
 
-    fn consume(&mut self) -> Result<(), ParseError> {
+    fn consume(&mut self) -> Result<(), ParseError<'a>> {
         self.next = try!(self.lexer.next_token());
         Ok(())
     }
 
-    fn expect(&mut self, token_type: TokenType<'a>) -> Result<(), ParseError> {
-        if self.next == token_type {
+    fn expect(&mut self, token_type: TokenType<'a>) -> Result<(), ParseError<'a>> {
+        if self.next.typ == token_type {
             try!(self.consume());
             Ok(())
+        } else if self.next.typ == TokenType::End {
+            Err(ParseError::UnexpectedEof { pos: self.next.pos })
+        } else {
+            Err(ParseError::UnexpectedToken {
+                found: self.next.typ,
+                expected: Some(token_type),
+                pos: self.next.pos,
+            })
+        }
+    }
+
+    fn expect_close_paren(&mut self, open_pos: u32) -> Result<(), ParseError<'a>> {
+        if self.next.typ == TokenType::OpSingle(')') {
+            self.consume()
         } else {
-            Err(ParseError::Parse(format!("Expected {:?} of expression, but got {:?} at position {:?}",
-                                          token_type, self.next.typ, self.next.pos)))
+            Err(ParseError::UnclosedParen { open_pos: open_pos })
         }
     }
 
-    fn parse_e(&mut self) -> Result<(), ParseError> {
+    fn parse_e(&mut self) -> Result<(), ParseError<'a>> {
         try!(self.parse_p());
-        while let Token { typ: TokenType::OpSingle(ch), pos } = self.next {
-            if !is_binary(ch) { break; }
-            self.push_operator(Op::Binary(ch, pos));
+        try!(self.parse_postfix());
+        while let Some(op) = self.next_infix_op() {
+            let pos = self.next.pos;
+            match op {
+                InfixOp::Binary(op) => try!(self.push_operator(Op::Binary(op, pos))),
+                InfixOp::Range => try!(self.push_operator(Op::Range(pos))),
+            }
             try!(self.consume());
             try!(self.parse_p());
+            try!(self.parse_postfix());
         }
         while !is_sentinel(&self.op_stack.last()) {
             self.pop_operator()
@@ -104,35 +221,94 @@ impl<'a> ShuntingYard<'a> {
         Ok(())
     }
 
-    fn parse_p(&mut self) -> Result<(), ParseError> {
+    fn next_infix_op(&mut self) -> Option<InfixOp> {
+        match self.next.typ {
+            TokenType::OpSingle(ch) if self.ops.is_binary(ch) => Some(InfixOp::Binary(self.ops.op_str(ch))),
+            TokenType::OpDouble('.', '.') => Some(InfixOp::Range),
+            TokenType::OpDouble(ch0, ch1) => double_op_str(ch0, ch1).map(InfixOp::Binary),
+            _ => None,
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<(), ParseError<'a>> {
+        while let Token { typ: TokenType::OpSingle(ch), pos } = self.next {
+            if !self.ops.is_postfix(ch) { break; }
+            try!(self.push_operator(Op::Postfix(ch, pos)));
+            try!(self.consume());
+        }
+        Ok(())
+    }
+
+    fn parse_p(&mut self) -> Result<(), ParseError<'a>> {
         match &self.next {
             &Token { typ: TokenType::Number(v), pos } => {
                 self.exp_stack.push(AstNode::new(Number(v), pos));
                 try!(self.consume());
             },
             &Token { typ: TokenType::Ident(s), pos } => {
-                self.exp_stack.push(AstNode::new(Ident(s.to_string()), pos));
+                let name = s.to_string();
                 try!(self.consume());
+                if let Token { typ: TokenType::OpSingle('('), .. } = self.next {
+                    try!(self.consume());
+                    self.op_stack.push(Op::Sentinel(pos));
+                    let mut args = Vec::new();
+                    if let Token { typ: TokenType::OpSingle(')'), .. } = self.next {
+                        try!(self.consume());
+                    } else {
+                        loop {
+                            try!(self.parse_e());
+                            args.push(self.exp_stack.pop().unwrap());
+                            match self.next {
+                                Token { typ: TokenType::OpSingle(','), .. } => {
+                                    try!(self.consume());
+                                },
+                                Token { typ: TokenType::OpSingle(')'), .. } => {
+                                    try!(self.consume());
+                                    break;
+                                },
+                                Token { typ: TokenType::End, pos } => {
+                                    return Err(ParseError::UnexpectedEof { pos: pos });
+                                },
+                                _ => return Err(ParseError::UnexpectedToken {
+                                    found: self.next.typ,
+                                    expected: Some(TokenType::OpSingle(')')),
+                                    pos: self.next.pos,
+                                }),
+                            }
+                        }
+                    }
+                    self.op_stack.pop().unwrap();
+                    self.exp_stack.push(AstNode::new(Func(name, args), pos));
+                } else {
+                    self.exp_stack.push(AstNode::new(Ident(name), pos));
+                }
             },
             &Token { typ: TokenType::OpSingle('('), pos } => {
                 try!(self.consume());
                 self.op_stack.push(Op::Sentinel(pos));
                 try!(self.parse_e());
-                try!(self.expect(TokenType::OpSingle(')')));
+                try!(self.expect_close_paren(pos));
                 self.op_stack.pop().unwrap();
                 let t = Box::new(self.exp_stack.pop().unwrap());
                 self.exp_stack.push(AstNode::new(Parens(t), pos));
             },
             &Token { typ: TokenType::OpSingle(ch), pos } => {
-                if !is_prefix(ch) {
-                    return Err(ParseError::Parse(format!("Expected unary operator, but got {:?}", ch)));
+                if !self.ops.is_prefix(ch) {
+                    return Err(ParseError::UnknownOperator { op: ch, pos: pos });
                 }
-                self.push_operator(Op::Prefix(ch, pos));
+                try!(self.push_operator(Op::Prefix(ch, pos)));
                 try!(self.consume());
                 try!(self.parse_p());
             },
+            &Token { typ: TokenType::End, pos } => {
+                return Err(ParseError::UnexpectedEof { pos: pos });
+            },
             _ => {
-                return Err(ParseError::Parse(format!("Unexpected token {:?}", self.next)));
+                return Err(ParseError::UnexpectedToken {
+                    found: self.next.typ,
+                    expected: None,
+                    pos: self.next.pos,
+                });
             }
         }
         Ok(())
@@ -152,15 +328,82 @@ impl<'a> ShuntingYard<'a> {
             },
             Op::Prefix(ch, pos) => self.exp_stack.push(AstNode::new(Prefix(ch, t), pos)),
             Op::Postfix(ch, pos) => self.exp_stack.push(AstNode::new(Postfix(ch, t), pos)),
+            Op::Range(pos) => {
+                let t0 = Box::new(self.exp_stack.pop().unwrap());
+                self.exp_stack.push(AstNode::new(Range(t0, t), pos));
+            },
             Op::Sentinel(pos) => panic!("Unexpected Sentinel from position {:?} on operator stack", pos),
         }
     }
 
-    fn push_operator(&mut self, op: Op) {
-        while has_greater_prec(self.top_operator(), &op) {
-           self.pop_operator();
+    fn push_operator(&mut self, op: Op) -> Result<(), ParseError<'a>> {
+        loop {
+            let top = *self.top_operator();
+            if !try!(self.has_greater_prec(&top, &op)) { break; }
+            self.pop_operator();
         }
         self.op_stack.push(op);
+        Ok(())
+    }
+
+    fn op_prec(&self, op: &Op) -> Result<i32, ParseError<'a>> {
+        match op {
+            &Op::Sentinel(_) => Ok(0),
+            &Op::Range(_) => Ok(1),
+            &Op::Binary(s, pos) => {
+                if let Some(p) = double_op_prec(s) {
+                    return Ok(p);
+                }
+                let ch = s.chars().next().unwrap();
+                match self.ops.lookup(ch, OperatorKind::Binary) {
+                    Some(spec) => Ok(spec.precedence as i32),
+                    None => Err(ParseError::UnknownOperator { op: ch, pos: pos }),
+                }
+            },
+            &Op::Prefix(ch, pos) => {
+                match self.ops.lookup(ch, OperatorKind::Prefix) {
+                    Some(spec) => Ok(spec.precedence as i32),
+                    None => Err(ParseError::UnknownOperator { op: ch, pos: pos }),
+                }
+            },
+            &Op::Postfix(ch, pos) => {
+                match self.ops.lookup(ch, OperatorKind::Postfix) {
+                    Some(spec) => Ok(spec.precedence as i32),
+                    None => Err(ParseError::UnknownOperator { op: ch, pos: pos }),
+                }
+            },
+        }
+    }
+
+    fn op_assoc(&self, op: &Op) -> Result<Assoc, ParseError<'a>> {
+        match op {
+            &Op::Range(_) => Ok(Assoc::Left),
+            &Op::Binary(s, pos) => {
+                if double_op_prec(s).is_some() {
+                    // every double-char operator introduced so far is left-associative
+                    return Ok(Assoc::Left);
+                }
+                let ch = s.chars().next().unwrap();
+                match self.ops.lookup(ch, OperatorKind::Binary) {
+                    Some(spec) => Ok(spec.assoc),
+                    None => Err(ParseError::UnknownOperator { op: ch, pos: pos }),
+                }
+            },
+            &Op::Postfix(ch, pos) => {
+                match self.ops.lookup(ch, OperatorKind::Postfix) {
+                    Some(spec) => Ok(spec.assoc),
+                    None => Err(ParseError::UnknownOperator { op: ch, pos: pos }),
+                }
+            },
+            _ => panic!("Operator {:?} does not have associativity", op),
+        }
+    }
+
+    #[inline(always)]
+    fn has_greater_prec(&self, op1: &Op, op2: &Op) -> Result<bool, ParseError<'a>> {
+        let prec1 = try!(self.op_prec(op1));
+        let prec2 = try!(self.op_prec(op2));
+        Ok(prec1 > prec2 || (prec1 == prec2 && try!(self.op_assoc(op1)) == Assoc::Left))
     }
 }
 
@@ -169,11 +412,24 @@ impl<'a> ShuntingYard<'a> {
 /// Shunting yard parser as described here
 ///   https://www.engr.mun.ca/~theo/Misc/exp_parsing.htm
 /// It parses the following grammar:
-///   E --> P {B P}
-///   P --> v | "(" E ")" | U P
-///   B --> "+" | "-" | "*" | "/" | "^"
+///   E --> P F {B P F} | E ".." E
+///   P --> v | "(" E ")" | U P | Identifier "(" [E {"," E}] ")"
+///   B --> "+" | "-" | "*" | "/" | "^" | "<" | "<=" | ">" | ">=" | "=" | "!=" | "&" | "|"
 ///   U --> "-"
-pub fn parse(text: &str) -> Result<AstNode, ParseError> {
+///   F --> {"!"}
+/// `B`'s fixity is governed by an `OperatorTable` (see its `Default` impl for
+/// the precedence ladder this grammar uses). A value is "truthy" if it is a
+/// non-zero number or a non-empty identifier, so relational/equality/boolean
+/// operators evaluate to `1` or `0` within the same numeric AST domain.
+pub fn parse<'a>(text: &'a str) -> Result<AstNode, ParseError<'a>> {
+    parse_with(text, &OperatorTable::default())
+}
+
+/// Like `parse`, but with a caller-supplied `OperatorTable` instead of the
+/// built-in arithmetic/relational/equality/boolean operator set. The `..`
+/// range operator and the double-char `<=`, `>=` and `!=` are recognized
+/// whatever the table holds.
+pub fn parse_with<'a, 'o>(text: &'a str, ops: &'o OperatorTable) -> Result<AstNode, ParseError<'a>> {
     let mut lexer = Lexer::new(text);
     let next = try!(lexer.next_token());
     ShuntingYard {
@@ -185,6 +441,7 @@ pub fn parse(text: &str) -> Result<AstNode, ParseError> {
             op_stack
         },
         exp_stack: Vec::new(),
+        ops: ops,
     }.parse()
 }
 
@@ -192,14 +449,31 @@ pub fn parse(text: &str) -> Result<AstNode, ParseError> {
 
 #[cfg(test)]
 mod test {
-    use super::parse;
+    use super::{parse, parse_with, OperatorTable, OperatorKind, Assoc};
 
     #[test]
     fn test() {
         let text = "(3*x+4)- 5*x+zy^2^3";
         println!("{}", text);
         println!("{}", parse(text).unwrap());
-        //parse("log(3x+4)- 5x zy^2^3").unwrap();
+        parse("log(3*x+4)- 5*x*zy^2^3").unwrap();
+        parse("f()").unwrap();
+        parse("3!").unwrap();
+        parse("(x+1)!^2").unwrap();
+        parse("3*x+4 > 5 & x != 0").unwrap();
+        parse("a <= b & c >= d | e = f").unwrap();
+        parse("1..n").unwrap();
+        parse("(x+1)..(y*2)").unwrap();
+        parse("1+1..n*2").unwrap();
+    }
+
+    #[test]
+    fn test_parse_with_custom_table() {
+        let mut ops = OperatorTable::new();
+        ops.define('+', OperatorKind::Binary, 1, Assoc::Left);
+        ops.define('%', OperatorKind::Binary, 2, Assoc::Left);
+        parse_with("3 % 2 + 1", &ops).unwrap();
+        assert!(parse_with("3 * 2", &ops).is_err());
     }
 }
 